@@ -0,0 +1,122 @@
+//! Scriptable modem/AT init sequence run once the serial port opens but
+//! before bridging starts (`-i <file>`).
+//!
+//! Each non-empty, non-comment (`#`) line is tab-separated:
+//! `<command>\t<expected>\t<timeout_ms>`. `expected` may be empty for a
+//! fire-and-forget command; `timeout_ms` defaults to 1000 if omitted.
+//! `\r`, `\n`, `\t` and `\\` are unescaped in both `command` and `expected`.
+
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio_serial::SerialStream;
+
+pub struct InitStep {
+    pub command: String,
+    pub expected: String,
+    pub timeout: Duration,
+}
+
+pub fn parse_script(contents: &str) -> std::result::Result<Vec<InitStep>, String> {
+    let mut steps = Vec::new();
+    for (lineno, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.split('\t');
+        let command = match fields.next() {
+            Some(command) => unescape(command),
+            None => return Err(format!("line {}: missing command", lineno + 1)),
+        };
+        let expected = unescape(fields.next().unwrap_or(""));
+        let timeout_ms: u64 = match fields.next() {
+            Some(ms) if !ms.is_empty() => match ms.parse() {
+                Ok(ms) => ms,
+                Err(_e) => return Err(format!("line {}: invaild timeout", lineno + 1)),
+            },
+            _ => 1000,
+        };
+        steps.push(InitStep {
+            command,
+            expected,
+            timeout: Duration::from_millis(timeout_ms),
+        });
+    }
+    Ok(steps)
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('r') => out.push('\r'),
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Writes each step's command and, unless `expected` is empty, waits up to
+/// `timeout` for that substring to show up in what the serial port sends
+/// back. Fails the whole sequence on the first timeout or I/O error.
+pub async fn run(
+    serial: &mut SerialStream,
+    steps: &[InitStep],
+    delay: Duration,
+) -> std::result::Result<(), String> {
+    let mut read_buf = [0u8; 256];
+    for step in steps {
+        println!("init: > {:?}", step.command);
+        if let Err(e) = serial.write_all(step.command.as_bytes()).await {
+            return Err(e.to_string());
+        }
+
+        if !step.expected.is_empty() {
+            let mut received = String::new();
+            let deadline = tokio::time::Instant::now() + step.timeout;
+            loop {
+                if received.contains(&step.expected) {
+                    break;
+                }
+                let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                if remaining.is_zero() {
+                    return Err(format!(
+                        "timed out waiting for {:?} after sending {:?}",
+                        step.expected, step.command
+                    ));
+                }
+                match tokio::time::timeout(remaining, serial.read(&mut read_buf)).await {
+                    Ok(Ok(0)) => {
+                        return Err("serial port closed during init sequence".to_string());
+                    }
+                    Ok(Ok(n)) => {
+                        received.push_str(&String::from_utf8_lossy(&read_buf[0..n]));
+                    }
+                    Ok(Err(e)) => {
+                        return Err(e.to_string());
+                    }
+                    Err(_elapsed) => {
+                        return Err(format!(
+                            "timed out waiting for {:?} after sending {:?}",
+                            step.expected, step.command
+                        ));
+                    }
+                }
+            }
+            println!("init: < {:?}", received);
+        }
+
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+    }
+    Ok(())
+}
@@ -1,42 +1,197 @@
+mod initscript;
+mod ratelimit;
+mod stats;
+mod telnet;
+
 use std::env;
 use std::io::Cursor;
 use std::io::Result;
 use std::net::SocketAddr;
 use std::net::ToSocketAddrs;
 use std::str::FromStr;
+use std::time::Duration;
 use tokio::io::AsyncReadExt;
 use tokio::io::AsyncWriteExt;
 use tokio::net::{TcpListener, TcpStream};
 use tokio_serial;
+use tokio_serial::SerialPort;
 use tokio_serial::SerialPortBuilderExt;
 use tokio_serial::SerialStream;
 
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_millis(250);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(4);
+const INIT_SCRIPT_DEFAULT_DELAY: Duration = Duration::from_millis(100);
+
 struct SerialConfig {
     name: String,
     baudrate: u32,
+    data_bits: tokio_serial::DataBits,
+    parity: tokio_serial::Parity,
+    stop_bits: tokio_serial::StopBits,
+    flow_control: tokio_serial::FlowControl,
 }
 
 impl SerialConfig {
+    /// Parses `name[,baudrate[,8N1[,flow]]]`, e.g. `/dev/ttyUSB0,115200,8N1,rtscts`.
+    /// Baudrate defaults to 115200, framing to 8N1 and flow control to none.
     fn form_str(serial_desc: &str) -> std::result::Result<SerialConfig, &str> {
-        match serial_desc.split_once(',') {
-            Some((name, baudrate)) => match baudrate.parse::<u32>() {
-                Err(_e) => {
-                    return Err("invaild baudrate");
-                }
-                Ok(baudrate) => {
-                    return Ok(SerialConfig {
-                        name: name.into(),
-                        baudrate,
-                    });
+        let mut parts = serial_desc.split(',');
+        let name = match parts.next() {
+            Some(name) => name,
+            None => return Err("missing serial port name"),
+        };
+        let mut cfg = SerialConfig {
+            name: name.into(),
+            baudrate: 115200,
+            data_bits: tokio_serial::DataBits::Eight,
+            parity: tokio_serial::Parity::None,
+            stop_bits: tokio_serial::StopBits::One,
+            flow_control: tokio_serial::FlowControl::None,
+        };
+
+        if let Some(baudrate) = parts.next() {
+            match baudrate.parse::<u32>() {
+                Ok(baudrate) => cfg.baudrate = baudrate,
+                Err(_e) => return Err("invaild baudrate"),
+            }
+        }
+
+        if let Some(framing) = parts.next() {
+            match parse_framing(framing) {
+                Ok((data_bits, parity, stop_bits)) => {
+                    cfg.data_bits = data_bits;
+                    cfg.parity = parity;
+                    cfg.stop_bits = stop_bits;
                 }
-            },
-            None => {
-                return Ok(SerialConfig {
-                    name: serial_desc.into(),
-                    baudrate: 115200,
-                })
+                Err(e) => return Err(e),
             }
         }
+
+        if let Some(flow_control) = parts.next() {
+            match parse_flow_control(flow_control) {
+                Ok(flow_control) => cfg.flow_control = flow_control,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(cfg)
+    }
+}
+
+/// Parses a `8N1`-style framing field into data bits, parity and stop bits.
+fn parse_framing(
+    framing: &str,
+) -> std::result::Result<
+    (tokio_serial::DataBits, tokio_serial::Parity, tokio_serial::StopBits),
+    &str,
+> {
+    let chars: Vec<char> = framing.chars().collect();
+    if chars.len() != 3 {
+        return Err("invaild framing, expected e.g. '8N1'");
+    }
+    let data_bits = match chars[0] {
+        '5' => tokio_serial::DataBits::Five,
+        '6' => tokio_serial::DataBits::Six,
+        '7' => tokio_serial::DataBits::Seven,
+        '8' => tokio_serial::DataBits::Eight,
+        _ => return Err("invaild data bits, expected 5-8"),
+    };
+    let parity = match chars[1].to_ascii_uppercase() {
+        'N' => tokio_serial::Parity::None,
+        'E' => tokio_serial::Parity::Even,
+        'O' => tokio_serial::Parity::Odd,
+        _ => return Err("invaild parity, expected N/E/O"),
+    };
+    let stop_bits = match chars[2] {
+        '1' => tokio_serial::StopBits::One,
+        '2' => tokio_serial::StopBits::Two,
+        _ => return Err("invaild stop bits, expected 1 or 2"),
+    };
+    Ok((data_bits, parity, stop_bits))
+}
+
+/// Parses the optional flow-control token: `none`, `rtscts` or `xonxoff`.
+fn parse_flow_control(flow: &str) -> std::result::Result<tokio_serial::FlowControl, &str> {
+    match flow {
+        "none" => Ok(tokio_serial::FlowControl::None),
+        "rtscts" => Ok(tokio_serial::FlowControl::Hardware),
+        "xonxoff" => Ok(tokio_serial::FlowControl::Software),
+        _ => Err("invaild flow control, expected none/rtscts/xonxoff"),
+    }
+}
+
+/// Applies one RFC 2217 COM-PORT-OPTION command to the serial line and
+/// returns the ack subnegotiation to echo back to the client.
+fn apply_com_port_command(serial: &mut SerialStream, cmd: telnet::ComPortCommand) -> Vec<u8> {
+    use telnet::ComPortCommand::*;
+    match &cmd {
+        SetBaudRate(baud) => {
+            let _ = serial.set_baud_rate(*baud);
+        }
+        SetDataSize(size) => {
+            if let Some(data_bits) = data_bits_from_rfc2217(*size) {
+                let _ = serial.set_data_bits(data_bits);
+            }
+        }
+        SetParity(parity) => {
+            if let Some(parity) = parity_from_rfc2217(*parity) {
+                let _ = serial.set_parity(parity);
+            }
+        }
+        SetStopSize(stop) => {
+            if let Some(stop_bits) = stop_bits_from_rfc2217(*stop) {
+                let _ = serial.set_stop_bits(stop_bits);
+            }
+        }
+        SetControl(control) => match *control {
+            telnet::CONTROL_DTR_ON => {
+                let _ = serial.write_data_terminal_ready(true);
+            }
+            telnet::CONTROL_DTR_OFF => {
+                let _ = serial.write_data_terminal_ready(false);
+            }
+            telnet::CONTROL_RTS_ON => {
+                let _ = serial.write_request_to_send(true);
+            }
+            telnet::CONTROL_RTS_OFF => {
+                let _ = serial.write_request_to_send(false);
+            }
+            telnet::CONTROL_BREAK_ON => {
+                let _ = serial.set_break();
+            }
+            telnet::CONTROL_BREAK_OFF => {
+                let _ = serial.clear_break();
+            }
+            _ => {}
+        },
+    }
+    telnet::ack_subnegotiation(&cmd)
+}
+
+fn data_bits_from_rfc2217(size: u8) -> Option<tokio_serial::DataBits> {
+    match size {
+        5 => Some(tokio_serial::DataBits::Five),
+        6 => Some(tokio_serial::DataBits::Six),
+        7 => Some(tokio_serial::DataBits::Seven),
+        8 => Some(tokio_serial::DataBits::Eight),
+        _ => None,
+    }
+}
+
+fn parity_from_rfc2217(parity: u8) -> Option<tokio_serial::Parity> {
+    match parity {
+        1 => Some(tokio_serial::Parity::None),
+        2 => Some(tokio_serial::Parity::Odd),
+        3 => Some(tokio_serial::Parity::Even),
+        _ => None,
+    }
+}
+
+fn stop_bits_from_rfc2217(stop: u8) -> Option<tokio_serial::StopBits> {
+    match stop {
+        1 => Some(tokio_serial::StopBits::One),
+        2 => Some(tokio_serial::StopBits::Two),
+        _ => None,
     }
 }
 
@@ -44,31 +199,82 @@ async fn exchange(
     mut socket: TcpStream,
     mut serial: SerialStream,
     buff_size: usize,
+    telnet_mode: bool,
+    report_stats: bool,
+    rate_limit: Option<usize>,
+    frame_gap: Option<Duration>,
 ) -> std::result::Result<(), String> {
     let mut socket_rx_buffer: Vec<u8> = Vec::with_capacity(buff_size);
     socket_rx_buffer.resize(buff_size, 0);
     let mut serial_rx_buffer: Vec<u8> = Vec::with_capacity(buff_size);
     serial_rx_buffer.resize(buff_size, 0);
+    let mut telnet_filter = telnet::TelnetFilter::new();
+    let mut stats = stats::Stats::new();
+    let mut stats_interval = tokio::time::interval(Duration::from_secs(1));
+    let mut rate_limiter = rate_limit.map(ratelimit::RateLimiter::new);
 
-    loop {
+    // Idle-gap packet framing (-g): bytes read from the serial port pile up
+    // here instead of going straight to the socket. The timer below is reset
+    // on every new byte and only fires the flush once the line has gone
+    // quiet, so a frame reaches the socket as one write.
+    let mut frame_buffer: Vec<u8> = Vec::with_capacity(buff_size);
+    let idle_timer = tokio::time::sleep(Duration::from_secs(3600));
+    tokio::pin!(idle_timer);
+
+    if telnet_mode {
+        if let Err(e) = socket.write_all(&telnet::negotiation_offer()).await {
+            return Err(e.to_string());
+        }
+    }
+
+    let result: std::result::Result<(), String> = 'pump: loop {
         tokio::select! {
             socket_nread = socket.read(&mut socket_rx_buffer) => {
                 match socket_nread {
                     Ok(nread) => {
                         if nread == 0 {
-                            break;
+                            break 'pump Ok(());
+                        } else if telnet_mode {
+                            let (data, commands) = telnet_filter.process(&socket_rx_buffer[0..nread]);
+                            for cmd in commands {
+                                let ack = apply_com_port_command(&mut serial, cmd);
+                                if let Err(e) = socket.write_all(&ack).await {
+                                    println!("error: {}", e);
+                                    break 'pump Ok(());
+                                }
+                            }
+                            if !data.is_empty() {
+                                if let Err(e) =
+                                    write_rate_limited(&mut serial, &data, rate_limiter.as_mut()).await
+                                {
+                                    break 'pump Err(e.to_string());
+                                }
+                            }
+                            if report_stats {
+                                stats.add_tx(data.len());
+                            }
                         } else {
-                            match serial.write(&socket_rx_buffer[0..nread]).await {
-                                Ok(_) => {}
+                            match write_rate_limited(
+                                &mut serial,
+                                &socket_rx_buffer[0..nread],
+                                rate_limiter.as_mut(),
+                            )
+                            .await
+                            {
+                                Ok(_) => {
+                                    if report_stats {
+                                        stats.add_tx(nread);
+                                    }
+                                }
                                 Err(e) => {
-                                    return Err(e.to_string());
+                                    break 'pump Err(e.to_string());
                                 }
                             }
                         }
                     }
                     Err(e) => {
                         println!("error: {}", e);
-                        return Ok(())
+                        break 'pump Ok(());
                     }
                 }
             }
@@ -76,33 +282,137 @@ async fn exchange(
             serial_nread = serial.read(&mut serial_rx_buffer) => {
                 match serial_nread {
                     Ok(nread) => {
-                        let mut cursor = Cursor::new(&serial_rx_buffer[0..nread]);
-                        match socket.write_buf(&mut cursor).await {
-                            Ok(nwrite) => {
-                                if nwrite == 0 {
-                                    break;
+                        if let Some(gap) = frame_gap {
+                            if nread == 0 {
+                                break 'pump Ok(());
+                            }
+                            frame_buffer.extend_from_slice(&serial_rx_buffer[0..nread]);
+                            if report_stats {
+                                stats.add_rx(nread);
+                            }
+                            if frame_buffer.len() >= buff_size {
+                                if let Err(e) = flush_frame(&mut socket, &mut frame_buffer, telnet_mode).await {
+                                    println!("error: {}", e);
+                                    break 'pump Ok(());
                                 }
+                            } else {
+                                idle_timer.as_mut().reset(tokio::time::Instant::now() + gap);
                             }
-                            Err(e) => {
-                                println!("error: {}", e);
-                                return Ok(())
+                        } else {
+                            let write_result = if telnet_mode {
+                                let escaped = telnet::escape_data(&serial_rx_buffer[0..nread]);
+                                socket.write_all(&escaped).await.map(|_| escaped.len())
+                            } else {
+                                let mut cursor = Cursor::new(&serial_rx_buffer[0..nread]);
+                                socket.write_buf(&mut cursor).await
+                            };
+                            match write_result {
+                                Ok(nwrite) => {
+                                    if nwrite == 0 {
+                                        break 'pump Ok(());
+                                    }
+                                    if report_stats {
+                                        stats.add_rx(nread);
+                                    }
+                                }
+                                Err(e) => {
+                                    println!("error: {}", e);
+                                    break 'pump Ok(());
+                                }
                             }
                         }
                     }
                     Err(e) => {
-                        return Err(e.to_string());
+                        break 'pump Err(e.to_string());
                     }
                 }
             }
+
+            _ = &mut idle_timer, if frame_gap.is_some() && !frame_buffer.is_empty() => {
+                if let Err(e) = flush_frame(&mut socket, &mut frame_buffer, telnet_mode).await {
+                    println!("error: {}", e);
+                    break 'pump Ok(());
+                }
+            }
+
+            _ = stats_interval.tick(), if report_stats => {
+                stats.report_rate();
+            }
+        }
+    };
+
+    if report_stats {
+        stats.report_total();
+    }
+    result
+}
+
+/// Writes `data` to `serial`, splitting it into pieces no larger than the
+/// rate limiter's configured bytes/sec and throttling before each piece.
+/// Without this, a single read bigger than `rate` (the common case: buffer
+/// sizes default to 512+ bytes while `-r` is meant for rates well under
+/// that) would pass through whole in one window, letting actual throughput
+/// run far above the configured limit.
+async fn write_rate_limited(
+    serial: &mut SerialStream,
+    data: &[u8],
+    rate_limiter: Option<&mut ratelimit::RateLimiter>,
+) -> std::io::Result<()> {
+    match rate_limiter {
+        Some(limiter) => {
+            for chunk in data.chunks(limiter.rate().max(1)) {
+                limiter.throttle(chunk.len()).await;
+                serial.write_all(chunk).await?;
+            }
+            Ok(())
         }
+        None => serial.write_all(data).await,
     }
-    Ok(())
+}
+
+/// Writes the whole accumulated frame to the socket in one shot, escaping it
+/// first if telnet COM-Port-Control is active, then clears `frame_buffer`.
+async fn flush_frame(
+    socket: &mut TcpStream,
+    frame_buffer: &mut Vec<u8>,
+    telnet_mode: bool,
+) -> std::result::Result<(), String> {
+    let result = if telnet_mode {
+        socket.write_all(&telnet::escape_data(frame_buffer)).await
+    } else {
+        socket.write_all(frame_buffer).await
+    };
+    frame_buffer.clear();
+    result.map_err(|e| e.to_string())
+}
+
+/// Bundles the transfer options `start_server`/`start_client` otherwise pass
+/// down as a long, easy-to-reorder run of same-typed positional parameters.
+/// `telnet_mode` (server-only) and `max_retries` (client-only) stay out of
+/// this, since each is meaningful to only one of the two callers.
+struct TransferOptions {
+    buffer_size: usize,
+    report_stats: bool,
+    rate_limit: Option<usize>,
+    frame_gap: Option<Duration>,
+    init_script: Option<Vec<initscript::InitStep>>,
+    init_delay: Duration,
+}
+
+fn open_serial(serial_cfg: &SerialConfig) -> tokio_serial::Result<SerialStream> {
+    tokio_serial::new(&serial_cfg.name, serial_cfg.baudrate)
+        .data_bits(serial_cfg.data_bits)
+        .parity(serial_cfg.parity)
+        .stop_bits(serial_cfg.stop_bits)
+        .flow_control(serial_cfg.flow_control)
+        .open_native_async()
 }
 
 async fn start_server(
     ip: SocketAddr,
     serial_cfg: SerialConfig,
-    buffer_size: usize,
+    telnet_mode: bool,
+    opts: TransferOptions,
 ) -> std::result::Result<(), String> {
     let listener = match TcpListener::bind(ip).await {
         Ok(l) => l,
@@ -115,15 +425,35 @@ async fn start_server(
         match listener.accept().await {
             Ok((socket, client_addr)) => {
                 println!("Accept {}", client_addr);
-                match tokio_serial::new(&serial_cfg.name, serial_cfg.baudrate).open_native_async() {
-                    Ok(serial) => match exchange(socket, serial, buffer_size).await {
-                        Ok(_) => {
-                            println!("Disconnect {}", client_addr);
+                match open_serial(&serial_cfg) {
+                    Ok(mut serial) => {
+                        if let Some(steps) = &opts.init_script {
+                            if let Err(e) =
+                                initscript::run(&mut serial, steps, opts.init_delay).await
+                            {
+                                println!("error: {}", e);
+                                continue;
+                            }
                         }
-                        Err(e) => {
-                            return Err(e);
+                        match exchange(
+                            socket,
+                            serial,
+                            opts.buffer_size,
+                            telnet_mode,
+                            opts.report_stats,
+                            opts.rate_limit,
+                            opts.frame_gap,
+                        )
+                        .await
+                        {
+                            Ok(_) => {
+                                println!("Disconnect {}", client_addr);
+                            }
+                            Err(e) => {
+                                return Err(e);
+                            }
                         }
-                    },
+                    }
                     Err(e) => {
                         return Err(format!(
                             "open serial port {}, baudrate = {} failed, {}",
@@ -139,12 +469,104 @@ async fn start_server(
     }
 }
 
+/// Client-mode supervisor: bridges `remote_ip` <-> serial, and on any
+/// non-fatal disconnect (EOF, connection reset, serial I/O error) reopens
+/// both ends after a capped exponential backoff. `max_retries == 0` means
+/// retry forever.
+async fn start_client(
+    remote_ip: SocketAddr,
+    serial_cfg: SerialConfig,
+    max_retries: u32,
+    opts: TransferOptions,
+) -> std::result::Result<(), String> {
+    let mut attempt: u32 = 0;
+    let mut backoff = RECONNECT_BACKOFF_MIN;
+
+    loop {
+        match TcpStream::connect(remote_ip).await {
+            Ok(socket) => match open_serial(&serial_cfg) {
+                Ok(mut serial) => {
+                    let init_ok = match &opts.init_script {
+                        Some(steps) => {
+                            match initscript::run(&mut serial, steps, opts.init_delay).await {
+                                Ok(_) => true,
+                                Err(e) => {
+                                    println!("error: {}", e);
+                                    false
+                                }
+                            }
+                        }
+                        None => true,
+                    };
+                    if init_ok {
+                        attempt = 0;
+                        backoff = RECONNECT_BACKOFF_MIN;
+                        match exchange(
+                            socket,
+                            serial,
+                            opts.buffer_size,
+                            false,
+                            opts.report_stats,
+                            opts.rate_limit,
+                            opts.frame_gap,
+                        )
+                        .await
+                        {
+                            Ok(_) => {
+                                println!("Disconnect {}", remote_ip);
+                            }
+                            Err(e) => {
+                                println!("error: {}", e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    println!(
+                        "open serial port {}, baudrate = {} failed, {}",
+                        &serial_cfg.name, &serial_cfg.baudrate, e.description
+                    );
+                }
+            },
+            Err(e) => {
+                println!("error: {}", e.to_string());
+            }
+        }
+
+        attempt += 1;
+        if max_retries != 0 && attempt > max_retries {
+            return Err(format!("giving up after {} attempts", attempt));
+        }
+        println!(
+            "reconnecting to {} in {:?} (attempt {})",
+            remote_ip, backoff, attempt
+        );
+        tokio::time::sleep(backoff).await;
+        backoff = std::cmp::min(backoff * 2, RECONNECT_BACKOFF_MAX);
+    }
+}
+
 fn print_usage(program: &str) {
     let help_info = r#"
     serial-name:    like 'COM1,115200' or '/dev/ttyUSB0', the default baudrate is 115200
+                    append a framing field like '8N1' (data bits, parity N/E/O, stop bits)
+                    and an optional flow-control token (none/rtscts/xonxoff), e.g.
+                    '/dev/ttyUSB0,115200,8N1,rtscts'
     -c              client mode, forward data to local serial-port
     -p              specific server-port, the default port is 8722
     -b              buffer size, 512 bytes by default
+    -t              server mode only, enable RFC 2217 telnet COM-Port-Control
+    -n              client mode only, max reconnect attempts, 0 = infinite (default)
+    -s              print throughput stats (bytes and bytes/sec per direction) every second
+    -r              rate-limit socket-to-serial bytes/sec, unlimited by default
+    -g              idle-gap packet framing: accumulate serial bytes and only
+                    forward them once this many microseconds pass with no new
+                    byte (or the buffer fills), preserving frame boundaries
+                    for protocols like Modbus RTU; off by default
+    -i              modem/AT init script run before bridging starts: tab-separated
+                    lines of '<command>\t<expected>\t<timeout_ms>', expected may be
+                    empty for fire-and-forget
+    -w              delay in milliseconds between init script commands, 100ms by default
     -h              help
 "#;
     print!("Usage: {} serial-name [ options ]{}", program, help_info);
@@ -157,6 +579,13 @@ async fn main() -> Result<()> {
     let mut server_port = 8722;
     let mut serial_cfg: Option<SerialConfig> = None;
     let mut buffer_size = 1024;
+    let mut telnet_mode = false;
+    let mut max_retries: u32 = 0;
+    let mut report_stats = false;
+    let mut rate_limit: Option<usize> = None;
+    let mut frame_gap: Option<Duration> = None;
+    let mut init_script_path: Option<String> = None;
+    let mut init_delay = INIT_SCRIPT_DEFAULT_DELAY;
     let program = args.next().unwrap();
     loop {
         match args.next() {
@@ -214,6 +643,88 @@ async fn main() -> Result<()> {
                     }
                 },
 
+                "-t" => {
+                    telnet_mode = true;
+                }
+
+                "-s" => {
+                    report_stats = true;
+                }
+
+                "-r" => match args.next() {
+                    Some(rate) => match rate.parse::<usize>() {
+                        Ok(rate) => {
+                            rate_limit = Some(rate);
+                        }
+                        Err(e) => {
+                            println!("error: {}", e);
+                            return Ok(());
+                        }
+                    },
+                    None => {
+                        println!("error: please specific bytes per second");
+                        return Ok(());
+                    }
+                },
+
+                "-g" => match args.next() {
+                    Some(gap) => match gap.parse::<u64>() {
+                        Ok(gap) => {
+                            frame_gap = Some(Duration::from_micros(gap));
+                        }
+                        Err(e) => {
+                            println!("error: {}", e);
+                            return Ok(());
+                        }
+                    },
+                    None => {
+                        println!("error: please specific idle gap in microseconds");
+                        return Ok(());
+                    }
+                },
+
+                "-i" => match args.next() {
+                    Some(path) => {
+                        init_script_path = Some(path);
+                    }
+                    None => {
+                        println!("error: please specific init script file");
+                        return Ok(());
+                    }
+                },
+
+                "-w" => match args.next() {
+                    Some(ms) => match ms.parse::<u64>() {
+                        Ok(ms) => {
+                            init_delay = Duration::from_millis(ms);
+                        }
+                        Err(e) => {
+                            println!("error: {}", e);
+                            return Ok(());
+                        }
+                    },
+                    None => {
+                        println!("error: please specific inter-command delay");
+                        return Ok(());
+                    }
+                },
+
+                "-n" => match args.next() {
+                    Some(retries) => match retries.parse::<u32>() {
+                        Ok(retries) => {
+                            max_retries = retries;
+                        }
+                        Err(e) => {
+                            println!("error: {}", e);
+                            return Ok(());
+                        }
+                    },
+                    None => {
+                        println!("error: please specific max retries");
+                        return Ok(());
+                    }
+                },
+
                 "-h" => {
                     print_usage(&program);
                     return Ok(());
@@ -239,47 +750,62 @@ async fn main() -> Result<()> {
         }
     }
 
+    let init_script = match &init_script_path {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(contents) => match initscript::parse_script(&contents) {
+                Ok(steps) => Some(steps),
+                Err(e) => {
+                    println!("error: invaild init script, {}", e);
+                    return Ok(());
+                }
+            },
+            Err(e) => {
+                println!("error: failed to read init script {}, {}", path, e);
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
     match serial_cfg {
         None => {
             println!("error: no serial port specified! Try '-h' for more information");
             return Ok(());
         }
         Some(serial_cfg) => match remote_ip {
-            Some(remote_ip) => match TcpStream::connect(remote_ip).await {
-                Err(e) => {
-                    println!("error: {}", e.to_string());
-                }
-                Ok(socket) => {
-                    match tokio_serial::new(&serial_cfg.name, serial_cfg.baudrate)
-                        .open_native_async()
-                    {
-                        Ok(serial) => match exchange(socket, serial, buffer_size).await {
-                            Ok(_) => {
-                                println!("Disconnect {}", remote_ip);
-                            }
-                            Err(e) => {
-                                println!("error: {}", e);
-                            }
-                        },
-                        Err(e) => {
-                            println!(
-                                "open serial port {}, baudrate = {} failed, {}",
-                                &serial_cfg.name, &serial_cfg.baudrate, e.description
-                            );
-                        }
-                    };
-                    return Ok(());
+            Some(remote_ip) => {
+                let opts = TransferOptions {
+                    buffer_size,
+                    report_stats,
+                    rate_limit,
+                    frame_gap,
+                    init_script,
+                    init_delay,
+                };
+                if let Err(e) = start_client(remote_ip, serial_cfg, max_retries, opts).await {
+                    println!("error: {}", e);
                 }
-            },
+                return Ok(());
+            }
             None => {
                 match ("0.0.0.0", server_port).to_socket_addrs() {
                     Ok(mut ips) => match ips.next() {
-                        Some(ip) => match start_server(ip, serial_cfg, buffer_size).await {
-                            Err(e) => {
-                                println!("error: {}", e);
+                        Some(ip) => {
+                            let opts = TransferOptions {
+                                buffer_size,
+                                report_stats,
+                                rate_limit,
+                                frame_gap,
+                                init_script,
+                                init_delay,
+                            };
+                            match start_server(ip, serial_cfg, telnet_mode, opts).await {
+                                Err(e) => {
+                                    println!("error: {}", e);
+                                }
+                                Ok(_) => {}
                             }
-                            Ok(_) => {}
-                        },
+                        }
                         None => {
                             println!("error: invaild ip address");
                             return Ok(());
@@ -0,0 +1,46 @@
+//! A tiny token-bucket helper for throttling the socket -> serial direction
+//! (`-r <bytes_per_sec>`), so a slow downstream serial device isn't flooded.
+
+use std::time::Duration;
+use tokio::time::Instant;
+
+pub struct RateLimiter {
+    rate: usize,
+    window_start: Instant,
+    sent_in_window: usize,
+}
+
+impl RateLimiter {
+    pub fn new(rate: usize) -> Self {
+        RateLimiter {
+            rate,
+            window_start: Instant::now(),
+            sent_in_window: 0,
+        }
+    }
+
+    /// The configured bytes/sec cap. Callers should split a write larger
+    /// than this into pieces no bigger than `rate` before calling
+    /// `throttle`, or a single oversized piece can pass through in one
+    /// window and blow past the configured rate.
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// Accounts for `n` more bytes about to be written, sleeping first if
+    /// that would exceed `rate` bytes/sec in the current one-second window.
+    pub async fn throttle(&mut self, n: usize) {
+        let now = Instant::now();
+        if now.duration_since(self.window_start) >= Duration::from_secs(1) {
+            self.window_start = now;
+            self.sent_in_window = 0;
+        }
+        if self.sent_in_window + n > self.rate {
+            let wait = self.window_start + Duration::from_secs(1) - now;
+            tokio::time::sleep(wait).await;
+            self.window_start = Instant::now();
+            self.sent_in_window = 0;
+        }
+        self.sent_in_window += n;
+    }
+}
@@ -0,0 +1,49 @@
+//! Per-direction byte counters for live throughput reporting (`-s`).
+
+pub struct Stats {
+    tx_total: u64,
+    rx_total: u64,
+    last_tx: u64,
+    last_rx: u64,
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats {
+            tx_total: 0,
+            rx_total: 0,
+            last_tx: 0,
+            last_rx: 0,
+        }
+    }
+
+    /// Records bytes forwarded socket -> serial.
+    pub fn add_tx(&mut self, n: usize) {
+        self.tx_total += n as u64;
+    }
+
+    /// Records bytes forwarded serial -> socket.
+    pub fn add_rx(&mut self, n: usize) {
+        self.rx_total += n as u64;
+    }
+
+    /// Prints bytes/sec since the last sample; call once per report interval.
+    pub fn report_rate(&mut self) {
+        let tx_rate = self.tx_total - self.last_tx;
+        let rx_rate = self.rx_total - self.last_rx;
+        println!(
+            "stats: tx {} B/s (total {}), rx {} B/s (total {})",
+            tx_rate, self.tx_total, rx_rate, self.rx_total
+        );
+        self.last_tx = self.tx_total;
+        self.last_rx = self.rx_total;
+    }
+
+    /// Prints cumulative totals; call once on disconnect.
+    pub fn report_total(&self) {
+        println!(
+            "stats: total tx {} bytes, rx {} bytes",
+            self.tx_total, self.rx_total
+        );
+    }
+}
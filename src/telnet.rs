@@ -0,0 +1,223 @@
+//! Minimal RFC 2217 (telnet COM-Port-Control) support.
+//!
+//! This only implements the slice of the telnet protocol `serialxy` needs to
+//! act as an RFC 2217 server: escaping/unescaping of `IAC` (0xFF) bytes in the
+//! data stream and parsing/building COM-PORT-OPTION (option 44) subnegotiations.
+
+pub const IAC: u8 = 255;
+pub const WILL: u8 = 251;
+pub const WONT: u8 = 252;
+pub const DO: u8 = 253;
+pub const DONT: u8 = 254;
+pub const SB: u8 = 250;
+pub const SE: u8 = 240;
+
+pub const COM_PORT_OPTION: u8 = 44;
+
+pub const SET_BAUDRATE: u8 = 1;
+pub const SET_DATASIZE: u8 = 2;
+pub const SET_PARITY: u8 = 3;
+pub const SET_STOPSIZE: u8 = 4;
+pub const SET_CONTROL: u8 = 5;
+
+pub const CONTROL_BREAK_ON: u8 = 5;
+pub const CONTROL_BREAK_OFF: u8 = 6;
+pub const CONTROL_DTR_ON: u8 = 8;
+pub const CONTROL_DTR_OFF: u8 = 9;
+pub const CONTROL_RTS_ON: u8 = 11;
+pub const CONTROL_RTS_OFF: u8 = 12;
+
+/// Upper bound on a buffered subnegotiation payload. Real COM-PORT-OPTION
+/// subnegotiations are at most a handful of bytes (`SET-BAUDRATE` is the
+/// largest, at 5); this just needs to be large enough to never reject a
+/// legitimate command while bounding how much a client that never sends
+/// `IAC SE` can make us buffer.
+const MAX_SB_LEN: usize = 64;
+
+/// A COM-PORT-OPTION subnegotiation command applied to the serial line.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ComPortCommand {
+    SetBaudRate(u32),
+    SetDataSize(u8),
+    SetParity(u8),
+    SetStopSize(u8),
+    SetControl(u8),
+}
+
+impl ComPortCommand {
+    /// The RFC 2217 command code, used to compute the `command + 100` ack.
+    pub fn code(&self) -> u8 {
+        match self {
+            ComPortCommand::SetBaudRate(_) => SET_BAUDRATE,
+            ComPortCommand::SetDataSize(_) => SET_DATASIZE,
+            ComPortCommand::SetParity(_) => SET_PARITY,
+            ComPortCommand::SetStopSize(_) => SET_STOPSIZE,
+            ComPortCommand::SetControl(_) => SET_CONTROL,
+        }
+    }
+
+    /// The payload bytes to echo back as part of the ack.
+    pub fn payload(&self) -> Vec<u8> {
+        match self {
+            ComPortCommand::SetBaudRate(baud) => baud.to_be_bytes().to_vec(),
+            ComPortCommand::SetDataSize(v)
+            | ComPortCommand::SetParity(v)
+            | ComPortCommand::SetStopSize(v)
+            | ComPortCommand::SetControl(v) => vec![*v],
+        }
+    }
+}
+
+#[derive(Default, PartialEq)]
+enum State {
+    #[default]
+    Data,
+    Iac,
+    Negotiate,
+    Sb,
+    SbIac,
+}
+
+/// Incremental telnet parser: splits a raw byte stream coming from the
+/// socket into plain data (forwarded to the serial port) and COM-PORT-OPTION
+/// commands, collapsing doubled `IAC` bytes back to a single `0xFF` along the
+/// way. Bytes may be fed in arbitrarily small chunks across calls.
+#[derive(Default)]
+pub struct TelnetFilter {
+    state: State,
+    sb_buffer: Vec<u8>,
+}
+
+impl TelnetFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed raw bytes just read from the socket. Returns the plain data to
+    /// write to serial and any COM-PORT-OPTION commands found in-band.
+    pub fn process(&mut self, input: &[u8]) -> (Vec<u8>, Vec<ComPortCommand>) {
+        let mut data = Vec::with_capacity(input.len());
+        let mut commands = Vec::new();
+        for &byte in input {
+            match self.state {
+                State::Data => {
+                    if byte == IAC {
+                        self.state = State::Iac;
+                    } else {
+                        data.push(byte);
+                    }
+                }
+                State::Iac => match byte {
+                    IAC => {
+                        data.push(IAC);
+                        self.state = State::Data;
+                    }
+                    SB => {
+                        self.sb_buffer.clear();
+                        self.state = State::Sb;
+                    }
+                    WILL | WONT | DO | DONT => {
+                        self.state = State::Negotiate;
+                    }
+                    _ => {
+                        // Other 2-byte telnet commands (NOP, AYT, ...) carry
+                        // no option byte and need no reply from us.
+                        self.state = State::Data;
+                    }
+                },
+                State::Negotiate => {
+                    // Option byte of a WILL/WONT/DO/DONT reply: we already
+                    // offered COM-PORT-OPTION up-front and have nothing
+                    // further to negotiate, so just consume it.
+                    self.state = State::Data;
+                }
+                State::Sb => {
+                    if byte == IAC {
+                        self.state = State::SbIac;
+                    } else if self.sb_buffer.len() >= MAX_SB_LEN {
+                        // Malformed or hostile subnegotiation that never ends
+                        // with IAC SE: drop what we buffered and bail back to
+                        // plain data rather than growing sb_buffer forever.
+                        self.sb_buffer.clear();
+                        self.state = State::Data;
+                    } else {
+                        self.sb_buffer.push(byte);
+                    }
+                }
+                State::SbIac => match byte {
+                    SE => {
+                        if let Some(cmd) = parse_subnegotiation(&self.sb_buffer) {
+                            commands.push(cmd);
+                        }
+                        self.state = State::Data;
+                    }
+                    IAC => {
+                        if self.sb_buffer.len() >= MAX_SB_LEN {
+                            self.sb_buffer.clear();
+                            self.state = State::Data;
+                        } else {
+                            self.sb_buffer.push(IAC);
+                            self.state = State::Sb;
+                        }
+                    }
+                    _ => {
+                        self.state = State::Data;
+                    }
+                },
+            }
+        }
+        (data, commands)
+    }
+}
+
+fn parse_subnegotiation(buf: &[u8]) -> Option<ComPortCommand> {
+    if buf.first() != Some(&COM_PORT_OPTION) {
+        return None;
+    }
+    let payload = &buf[1..];
+    match *payload.first()? {
+        SET_BAUDRATE if payload.len() >= 5 => Some(ComPortCommand::SetBaudRate(
+            u32::from_be_bytes([payload[1], payload[2], payload[3], payload[4]]),
+        )),
+        SET_DATASIZE if payload.len() >= 2 => Some(ComPortCommand::SetDataSize(payload[1])),
+        SET_PARITY if payload.len() >= 2 => Some(ComPortCommand::SetParity(payload[1])),
+        SET_STOPSIZE if payload.len() >= 2 => Some(ComPortCommand::SetStopSize(payload[1])),
+        SET_CONTROL if payload.len() >= 2 => Some(ComPortCommand::SetControl(payload[1])),
+        _ => None,
+    }
+}
+
+/// Bytes to send right after accept() to offer COM-PORT-OPTION negotiation.
+pub fn negotiation_offer() -> [u8; 6] {
+    [IAC, WILL, COM_PORT_OPTION, IAC, DO, COM_PORT_OPTION]
+}
+
+/// Builds the server's subnegotiation acknowledgement for a command that was
+/// just applied: `command + 100` echoed back with the same payload. Payload
+/// bytes are escaped like any other in-band data, since a raw `0xFF` inside
+/// it would otherwise be read as the start of the closing `IAC SE`.
+pub fn ack_subnegotiation(cmd: &ComPortCommand) -> Vec<u8> {
+    let payload = escape_data(&cmd.payload());
+    let mut out = Vec::with_capacity(payload.len() + 6);
+    out.push(IAC);
+    out.push(SB);
+    out.push(COM_PORT_OPTION);
+    out.push(cmd.code() + 100);
+    out.extend_from_slice(&payload);
+    out.push(IAC);
+    out.push(SE);
+    out
+}
+
+/// Doubles every `IAC` (0xFF) byte so raw serial data survives the telnet
+/// layer when written back to the socket.
+pub fn escape_data(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(input.len());
+    for &byte in input {
+        out.push(byte);
+        if byte == IAC {
+            out.push(IAC);
+        }
+    }
+    out
+}